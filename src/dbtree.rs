@@ -0,0 +1,91 @@
+//! Groups an fdb's table names into a collapsible tree, independent of how
+//! it ends up rendered - `main.rs` turns a [`Tree`] into the sidebar's
+//! `gtk::TreeStore` and keeps it filtered as the user types.
+
+/// One node of a [`Tree`]: either a table leaf, or a named group of table
+/// leaves sharing a common prefix.
+pub enum DatabaseTreeItem {
+    Group {
+        name: String,
+        children: Vec<DatabaseTreeItem>,
+    },
+    Table {
+        name: String,
+    },
+}
+
+impl DatabaseTreeItem {
+    /// True if this node is a table whose name contains `query`, or a
+    /// group with such a table anywhere below it.
+    pub fn matches(&self, query: &str) -> bool {
+        match self {
+            DatabaseTreeItem::Table { name } => name.to_lowercase().contains(query),
+            DatabaseTreeItem::Group { children, .. } => children.iter().any(|c| c.matches(query)),
+        }
+    }
+}
+
+/// A forest of [`DatabaseTreeItem`]s.
+pub struct Tree {
+    pub roots: Vec<DatabaseTreeItem>,
+}
+
+/// The key tables are grouped by: everything before `separator` if given
+/// and present (e.g. `Brick_IDTable` groups under `Brick`), otherwise
+/// everything up to the first camelCase boundary (e.g. `MissionTasks`
+/// groups under `Mission`).
+fn group_key(name: &str, separator: Option<char>) -> String {
+    if let Some(sep) = separator {
+        if let Some(idx) = name.find(sep) {
+            return name[..idx].to_string();
+        }
+    }
+    let bytes = name.as_bytes();
+    for i in 1..bytes.len() {
+        let prev = bytes[i - 1] as char;
+        let cur = bytes[i] as char;
+        if prev.is_lowercase() && cur.is_uppercase() {
+            return name[..i].to_string();
+        }
+    }
+    name.to_string()
+}
+
+impl Tree {
+    /// Groups `names` by [`group_key`]; a key shared by only one table is
+    /// left as a bare leaf rather than wrapped in a single-child group.
+    pub fn from_table_names<I>(names: I, separator: Option<char>) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+        for name in names {
+            let key = group_key(&name, separator);
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, members)) => members.push(name),
+                None => groups.push((key, vec![name])),
+            }
+        }
+
+        let roots = groups
+            .into_iter()
+            .map(|(key, mut members)| {
+                if members.len() == 1 {
+                    DatabaseTreeItem::Table {
+                        name: members.remove(0),
+                    }
+                } else {
+                    DatabaseTreeItem::Group {
+                        name: key,
+                        children: members
+                            .into_iter()
+                            .map(|name| DatabaseTreeItem::Table { name })
+                            .collect(),
+                    }
+                }
+            })
+            .collect();
+
+        Tree { roots }
+    }
+}
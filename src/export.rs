@@ -0,0 +1,342 @@
+//! Exporting an opened FDB [`Database`] to other file formats.
+//!
+//! [`DbExporter`] is the common sink every format writes through; the UI
+//! only has to pick an implementation based on the file extension (or an
+//! explicit format choice) and drive it with the same
+//! `table.column_iter()` / `table.row_iter()` traversal.
+
+use assembly_data::fdb::{
+    align::{Database, Field},
+    core::ValueType,
+};
+use std::{
+    error::Error,
+    fmt::{self, Write as _},
+    fs::{self, File},
+    io::{BufWriter, Write as _},
+    path::{Path, PathBuf},
+};
+
+pub type ExportResult<T> = Result<T, Box<dyn Error>>;
+
+/// An owned, 'static copy of a [`Field`], for passing across exporter calls
+/// without tying them to the lifetime of the row that produced it.
+pub enum ExportValue {
+    Null,
+    Integer(i32),
+    Float(f32),
+    Text(String),
+    Boolean(bool),
+    BigInt(i64),
+}
+
+impl rusqlite::ToSql for ExportValue {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        use rusqlite::types::Value;
+        let v = match self {
+            ExportValue::Null => Value::Null,
+            ExportValue::Integer(i) => Value::Integer((*i).into()),
+            ExportValue::Float(f) => Value::Real((*f).into()),
+            ExportValue::Text(s) => Value::Text(s.clone()),
+            ExportValue::Boolean(b) => Value::Integer(if *b { 1 } else { 0 }),
+            ExportValue::BigInt(i) => Value::Integer(*i),
+        };
+        Ok(rusqlite::types::ToSqlOutput::Owned(v))
+    }
+}
+
+impl From<Field<'_>> for ExportValue {
+    fn from(field: Field) -> Self {
+        match field {
+            Field::Nothing => ExportValue::Null,
+            Field::Integer(v) => ExportValue::Integer(v),
+            Field::Float(v) => ExportValue::Float(v),
+            Field::Text(v) => ExportValue::Text(v.decode().into_owned()),
+            Field::Boolean(v) => ExportValue::Boolean(v),
+            Field::BigInt(v) => ExportValue::BigInt(v),
+            Field::VarChar(v) => ExportValue::Text(v.decode().into_owned()),
+        }
+    }
+}
+
+/// A sink that a [`Database`] can be drained into, one table at a time.
+pub trait DbExporter {
+    fn begin_table(&mut self, name: &str, columns: &[(String, ValueType)]) -> ExportResult<()>;
+    fn write_row(&mut self, fields: &[ExportValue]) -> ExportResult<()>;
+    fn finish(&mut self) -> ExportResult<()>;
+}
+
+/// Drives `exporter` over every table in `db`, reusing the same column/row
+/// traversal regardless of which [`DbExporter`] is plugged in.
+pub fn export_database(exporter: &mut dyn DbExporter, db: Database) -> ExportResult<()> {
+    let tables = db.tables();
+    for table in tables.iter() {
+        let columns: Vec<(String, ValueType)> = table
+            .column_iter()
+            .map(|col| (col.name().as_ref().to_string(), col.value_type()))
+            .collect();
+        exporter.begin_table(table.name().as_ref(), &columns)?;
+
+        for row in table.row_iter() {
+            let fields: Vec<ExportValue> = row.field_iter().map(ExportValue::from).collect();
+            exporter.write_row(&fields)?;
+        }
+    }
+    exporter.finish()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    Sqlite,
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "sqlite" | "db" => Some(ExportFormat::Sqlite),
+            "csv" => Some(ExportFormat::Csv),
+            "jsonl" | "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn default_file_name(self) -> &'static str {
+        match self {
+            ExportFormat::Sqlite => "export.sqlite",
+            ExportFormat::Csv => "export.csv",
+            ExportFormat::Json => "export.jsonl",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct UnknownColumnType(u8);
+
+impl fmt::Display for UnknownColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown column type {}", self.0)
+    }
+}
+
+impl Error for UnknownColumnType {}
+
+/// Writes every table into its own table in a SQLite database, mirroring
+/// the original hand-rolled `try_export_db`.
+pub struct SqliteExporter {
+    conn: rusqlite::Connection,
+    insert_query: String,
+}
+
+impl SqliteExporter {
+    pub fn create(path: &Path) -> ExportResult<Self> {
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute("BEGIN", rusqlite::params![])?;
+        Ok(Self {
+            conn,
+            insert_query: String::new(),
+        })
+    }
+}
+
+impl DbExporter for SqliteExporter {
+    fn begin_table(&mut self, name: &str, columns: &[(String, ValueType)]) -> ExportResult<()> {
+        let mut create_query = format!("CREATE TABLE IF NOT EXISTS \"{}\"\n(\n", name);
+        let mut insert_query = format!("INSERT INTO \"{}\" (", name);
+        let mut first = true;
+        for (col_name, value_type) in columns {
+            if first {
+                first = false;
+            } else {
+                create_query.push_str(",\n");
+                insert_query.push_str(", ");
+            }
+            let typ = match value_type {
+                ValueType::Nothing => "NULL",
+                ValueType::Integer => "INTEGER",
+                ValueType::Float => "REAL",
+                ValueType::Text => "TEXT",
+                ValueType::Boolean => "INTEGER",
+                ValueType::BigInt => "INTEGER",
+                ValueType::VarChar => "BLOB",
+                ValueType::Unknown(k) => return Err(Box::new(UnknownColumnType(*k))),
+            };
+            create_query.push_str(&format!("    [{}] {}", col_name, typ));
+            insert_query.push_str(&format!("[{}]", col_name));
+        }
+        create_query.push_str(");");
+        insert_query.push_str(") VALUES (?1");
+        for i in 2..=columns.len() {
+            insert_query.push_str(&format!(", ?{}", i));
+        }
+        insert_query.push_str(");");
+
+        self.conn.execute(&create_query, rusqlite::params![])?;
+        self.insert_query = insert_query;
+        Ok(())
+    }
+
+    fn write_row(&mut self, fields: &[ExportValue]) -> ExportResult<()> {
+        self.conn.execute(&self.insert_query, fields.iter())?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> ExportResult<()> {
+        self.conn.execute("COMMIT", rusqlite::params![])?;
+        Ok(())
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Writes one CSV file per table into `dir`, named `<table>.csv`.
+pub struct CsvExporter {
+    dir: PathBuf,
+    current: Option<BufWriter<File>>,
+}
+
+impl CsvExporter {
+    pub fn create(dir: &Path) -> ExportResult<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            current: None,
+        })
+    }
+}
+
+impl DbExporter for CsvExporter {
+    fn begin_table(&mut self, name: &str, columns: &[(String, ValueType)]) -> ExportResult<()> {
+        if let Some(mut file) = self.current.take() {
+            file.flush()?;
+        }
+        let path = self.dir.join(format!("{}.csv", name));
+        let mut file = BufWriter::new(File::create(path)?);
+        let header: Vec<String> = columns.iter().map(|(n, _)| csv_escape(n)).collect();
+        writeln!(file, "{}", header.join(","))?;
+        self.current = Some(file);
+        Ok(())
+    }
+
+    fn write_row(&mut self, fields: &[ExportValue]) -> ExportResult<()> {
+        let file = self.current.as_mut().expect("begin_table not called");
+        let row: Vec<String> = fields
+            .iter()
+            .map(|f| {
+                csv_escape(&match f {
+                    ExportValue::Null => String::new(),
+                    ExportValue::Integer(i) => i.to_string(),
+                    // `NaN`/`inf` aren't meaningful CSV cell values either;
+                    // leave the cell blank like a `Null` field would be.
+                    ExportValue::Float(v) if v.is_finite() => v.to_string(),
+                    ExportValue::Float(_) => String::new(),
+                    ExportValue::Text(s) => s.clone(),
+                    ExportValue::Boolean(b) => b.to_string(),
+                    ExportValue::BigInt(i) => i.to_string(),
+                })
+            })
+            .collect();
+        writeln!(file, "{}", row.join(","))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> ExportResult<()> {
+        if let Some(mut file) = self.current.take() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Writes every row of every table as one JSON object per line, tagged
+/// with its source table so a single file can hold the whole database.
+pub struct JsonExporter {
+    file: BufWriter<File>,
+    table_name: String,
+    column_names: Vec<String>,
+}
+
+impl JsonExporter {
+    pub fn create(path: &Path) -> ExportResult<Self> {
+        Ok(Self {
+            file: BufWriter::new(File::create(path)?),
+            table_name: String::new(),
+            column_names: Vec::new(),
+        })
+    }
+}
+
+impl DbExporter for JsonExporter {
+    fn begin_table(&mut self, name: &str, columns: &[(String, ValueType)]) -> ExportResult<()> {
+        self.table_name = name.to_string();
+        self.column_names = columns.iter().map(|(n, _)| n.clone()).collect();
+        Ok(())
+    }
+
+    fn write_row(&mut self, fields: &[ExportValue]) -> ExportResult<()> {
+        let mut line = String::from("{");
+        write!(line, "\"_table\":{}", json_escape(&self.table_name))?;
+        for (name, field) in self.column_names.iter().zip(fields) {
+            line.push(',');
+            write!(line, "{}:", json_escape(name))?;
+            match field {
+                ExportValue::Null => line.push_str("null"),
+                ExportValue::Integer(i) => write!(line, "{}", i)?,
+                ExportValue::Float(v) => {
+                    // `NaN`/`inf` have no JSON representation; write `null`
+                    // rather than emitting a value that would fail to parse.
+                    if v.is_finite() {
+                        write!(line, "{}", v)?
+                    } else {
+                        line.push_str("null")
+                    }
+                }
+                ExportValue::Text(s) => line.push_str(&json_escape(s)),
+                ExportValue::Boolean(b) => write!(line, "{}", b)?,
+                ExportValue::BigInt(i) => write!(line, "{}", i)?,
+            }
+        }
+        line.push('}');
+        writeln!(self.file, "{}", line)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> ExportResult<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Picks the exporter matching `format` and drains `db` into `path` through
+/// it, matching the structure of the original single-format `try_export_db`.
+pub fn try_export_db(path: &Path, db: Database, format: ExportFormat) -> ExportResult<()> {
+    match format {
+        ExportFormat::Sqlite => export_database(&mut SqliteExporter::create(path)?, db),
+        ExportFormat::Csv => export_database(&mut CsvExporter::create(path)?, db),
+        ExportFormat::Json => export_database(&mut JsonExporter::create(path)?, db),
+    }
+}
@@ -1,229 +1,293 @@
+mod dbtree;
+mod export;
+mod model;
+mod pklookup;
+
 use assembly_data::fdb::{
-    align::{Database, Field, Row, Table},
+    align::{Database, Field, Table},
     core::ValueType,
 };
+use dbtree::{DatabaseTreeItem, Tree};
+use export::ExportFormat;
 use gio::prelude::*;
 use gtk::{prelude::*, TreeView};
 use memmap::Mmap;
-use rusqlite::{types::ToSqlOutput, ToSql};
-use std::{
-    cell::RefCell,
-    convert::TryFrom,
-    fmt::Write,
-    fs::File,
-    io,
-    ops::{Deref, Range},
-    path::Path,
-    rc::Rc,
-    time::Instant,
-};
+use model::FdbTreeModel;
+use std::{cell::RefCell, convert::TryFrom, fs::File, io, path::Path, rc::Rc};
 
 struct DB {
-    mmap: Mmap,
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Paging {
-    num_pages: usize,
-    current: usize,
+    mmap: Rc<Mmap>,
 }
 
 struct TablePage {
     name: glib::GString,
-    store: gtk::TreeStore,
+    model: FdbTreeModel,
+    filter: gtk::TreeModelFilter,
+    col_count: usize,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+struct ColumnSort {
+    column: Option<i32>,
+    reverse: bool,
 }
 
 fn try_load_file(path: &Path) -> io::Result<DB> {
     let _file = File::open(path)?;
     let mmap = unsafe { Mmap::map(&_file)? };
-    Ok(DB { mmap })
+    Ok(DB {
+        mmap: Rc::new(mmap),
+    })
 }
 
-pub struct SqliteVal<'a>(Field<'a>);
-
-impl<'a> ToSql for SqliteVal<'a> {
-    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
-        use rusqlite::types::Value;
-        let r = match self.0 {
-            Field::Nothing => Value::Null,
-            Field::Integer(i) => Value::Integer(i.into()),
-            Field::Float(f) => Value::Real(f.into()),
-            Field::Text(s) => Value::Text(s.decode().into_owned()),
-            Field::Boolean(b) => Value::Integer(if b { 1 } else { 0 }),
-            Field::BigInt(i) => Value::Integer(i),
-            Field::VarChar(b) => Value::Text(b.decode().into_owned()),
-        };
-        Ok(ToSqlOutput::Owned(r))
+/// Converts a single decoded [`Field`] into the `glib::Value` a
+/// `gtk::TreeModel` hands back from `get_value` - the one place that used
+/// to need `std::mem::transmute` to fake a `'static` lifetime, now that
+/// each field is decoded into its own owned value instead of being
+/// borrowed out of a row buffer.
+pub(crate) fn field_to_value(field: Field) -> glib::Value {
+    match field {
+        Field::Nothing => glib::Value::from_type(glib::Type::Invalid),
+        Field::Integer(v) => v.to_value(),
+        Field::Float(v) => v.to_value(),
+        Field::Text(v) => v.decode().into_owned().to_value(),
+        Field::Boolean(v) => v.to_value(),
+        Field::BigInt(v) => v.to_value(),
+        Field::VarChar(v) => v.decode().into_owned().to_value(),
     }
 }
 
-struct Iter<'a> {
-    inner: Box<dyn Iterator<Item = Field<'a>> + 'a>,
-}
-
-impl<'a> Iterator for Iter<'a> {
-    type Item = SqliteVal<'a>;
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(SqliteVal)
+/// Parses a cell editor's raw text back into the `glib::Value` type
+/// [`field_to_value`] would have produced for this column - the inverse
+/// conversion an edited cell needs before [`FdbTreeModel::set_value`] can
+/// write it back. `None` means the text didn't parse as the column's type,
+/// so the edit is dropped rather than corrupting the cell with a mismatched
+/// `glib::Value`.
+fn parse_edit(value_type: ValueType, text: &str) -> Option<glib::Value> {
+    match value_type {
+        ValueType::Integer => text.trim().parse::<i32>().ok().map(|v| v.to_value()),
+        ValueType::Float => text.trim().parse::<f32>().ok().map(|v| v.to_value()),
+        ValueType::BigInt => text.trim().parse::<i64>().ok().map(|v| v.to_value()),
+        ValueType::Boolean => match text.trim().to_lowercase().as_str() {
+            "true" | "1" => Some(true.to_value()),
+            "false" | "0" => Some(false.to_value()),
+            _ => None,
+        },
+        ValueType::Text | ValueType::VarChar | ValueType::Nothing | ValueType::Unknown(_) => {
+            Some(text.to_value())
+        }
     }
 }
 
-struct SqliteRow<'a>(Row<'a>);
+/// A parsed search-bar query: either a substring match (the default) or,
+/// for a `"low..high"` query, an inclusive numeric range - the two match
+/// modes folded into the one `TreeModelFilter::visible_func` already
+/// driving live search, instead of a second filter-model GObject exposing
+/// a match-mode property.
+enum RowQuery<'a> {
+    Substring(&'a str),
+    Range(f64, f64),
+}
 
-impl<'a> IntoIterator for SqliteRow<'a> {
-    type IntoIter = Iter<'a>;
-    type Item = SqliteVal<'a>;
-    fn into_iter(self) -> Self::IntoIter {
-        Iter {
-            inner: Box::new(self.0.field_iter()),
+impl<'a> RowQuery<'a> {
+    fn parse(text: &'a str) -> Self {
+        if let Some((low, high)) = text.split_once("..") {
+            if let (Ok(low), Ok(high)) = (low.trim().parse::<f64>(), high.trim().parse::<f64>()) {
+                return RowQuery::Range(low.min(high), low.max(high));
+            }
         }
+        RowQuery::Substring(text)
     }
 }
 
-fn try_export_db(path: &Path, db: Database) -> rusqlite::Result<()> {
-    let start = Instant::now();
-    let conn = rusqlite::Connection::open(path)?;
-
-    conn.execute("BEGIN", rusqlite::params![])?;
-
-    let tables = db.tables();
-    for table in tables.iter() {
-        let mut create_query = format!("CREATE TABLE IF NOT EXISTS \"{}\"\n(\n", table.name());
-        let mut insert_query = format!("INSERT INTO \"{}\" (", table.name());
-        let mut first = true;
-        for col in table.column_iter() {
-            if first {
-                first = false;
-            } else {
-                writeln!(create_query, ",").unwrap();
-                write!(insert_query, ", ").unwrap();
+/// Whether any of `model`'s `col_count` columns at `iter` matches `query` -
+/// a substring contained in the cell's stringified value (case-insensitive),
+/// or a numeric cell falling inside an inclusive range.
+fn row_contains(
+    model: &gtk::TreeModel,
+    iter: &gtk::TreeIter,
+    col_count: usize,
+    query: &RowQuery,
+) -> bool {
+    for i in 0..col_count {
+        let value = model.get_value(iter, i as i32);
+        let matched = match query {
+            RowQuery::Substring(needle) => {
+                if let Ok(Some(s)) = value.get::<String>() {
+                    s.to_lowercase().contains(needle)
+                } else if let Ok(Some(v)) = value.get::<i32>() {
+                    v.to_string().contains(needle)
+                } else if let Ok(Some(v)) = value.get::<i64>() {
+                    v.to_string().contains(needle)
+                } else if let Ok(Some(v)) = value.get::<f32>() {
+                    v.to_string().contains(needle)
+                } else if let Ok(Some(v)) = value.get::<bool>() {
+                    v.to_string().contains(needle)
+                } else {
+                    false
+                }
             }
-            let typ = match col.value_type() {
-                ValueType::Nothing => "NULL",
-                ValueType::Integer => "INTEGER",
-                ValueType::Float => "REAL",
-                ValueType::Text => "TEXT",
-                ValueType::Boolean => "INTEGER",
-                ValueType::BigInt => "INTEGER",
-                ValueType::VarChar => "BLOB",
-                ValueType::Unknown(_) => panic!(),
-            };
-            write!(create_query, "    [{}] {}", col.name(), typ).unwrap();
-            write!(insert_query, "[{}]", col.name()).unwrap();
-        }
-        create_query.push_str(");");
-        insert_query.push_str(") VALUES (?1");
-        for i in 2..=table.column_count() {
-            write!(insert_query, ", ?{}", i).unwrap();
-        }
-        insert_query.push_str(");");
-        println!("{}", insert_query);
-        conn.execute(&create_query, rusqlite::params![])?;
-
-        let mut stmt = conn.prepare(&insert_query)?;
-        for row in table.row_iter() {
-            stmt.execute(SqliteRow(row))?;
+            RowQuery::Range(low, high) => {
+                if let Ok(Some(v)) = value.get::<i32>() {
+                    (f64::from(v) >= *low) && (f64::from(v) <= *high)
+                } else if let Ok(Some(v)) = value.get::<i64>() {
+                    (v as f64 >= *low) && (v as f64 <= *high)
+                } else if let Ok(Some(v)) = value.get::<f32>() {
+                    (f64::from(v) >= *low) && (f64::from(v) <= *high)
+                } else {
+                    false
+                }
+            }
+        };
+        if matched {
+            return true;
         }
     }
-
-    conn.execute("COMMIT", rusqlite::params![])?;
-
-    let duration = start.elapsed();
-    println!(
-        "Export finished in {}.{}s",
-        duration.as_secs(),
-        duration.as_millis() % 1000
-    );
-    Ok(())
+    false
 }
 
-pub enum RefField {
-    Integer(i32),
-    Float(f32),
-    Text(String),
-    Boolean(bool),
-    BigInt(i64),
-    VarChar(String),
+/// Stringifies a single cell the same way [`row_contains`] does, for
+/// copying a selection out to the clipboard.
+fn value_to_string(model: &gtk::TreeModel, iter: &gtk::TreeIter, column: i32) -> String {
+    let value = model.get_value(iter, column);
+    if let Ok(Some(s)) = value.get::<String>() {
+        s
+    } else if let Ok(Some(v)) = value.get::<i32>() {
+        v.to_string()
+    } else if let Ok(Some(v)) = value.get::<i64>() {
+        v.to_string()
+    } else if let Ok(Some(v)) = value.get::<f32>() {
+        v.to_string()
+    } else if let Ok(Some(v)) = value.get::<bool>() {
+        v.to_string()
+    } else {
+        String::new()
+    }
 }
 
-impl RefField {
-    fn from(field: Field) -> Option<Self> {
-        match field {
-            Field::Nothing => None,
-            Field::Integer(iv) => Some(RefField::Integer(iv)),
-            Field::Float(fv) => Some(RefField::Float(fv)),
-            Field::Text(tv) => Some(RefField::Text(tv.decode().into_owned())),
-            Field::Boolean(bv) => Some(RefField::Boolean(bv)),
-            Field::BigInt(iv) => Some(RefField::BigInt(iv)),
-            Field::VarChar(vv) => Some(RefField::VarChar(vv.decode().into_owned())),
+/// Renders one row as a JSON object, reusing [`export::json_escape`] so the
+/// popover's "Copy Row as JSON" escapes strings the way the JSON exporter
+/// does. Unlike the exporter's lines, the object has no `_table` key, and
+/// floats are written with `to_string` even when they are `NaN`/`inf`.
+fn row_to_json(model: &gtk::TreeModel, iter: &gtk::TreeIter, column_names: &[String]) -> String {
+    let mut out = String::from("{");
+    for (i, name) in column_names.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let value = model.get_value(iter, i as i32);
+        out.push_str(&export::json_escape(name));
+        out.push(':');
+        if let Ok(Some(s)) = value.get::<String>() {
+            out.push_str(&export::json_escape(&s));
+        } else if let Ok(Some(v)) = value.get::<i32>() {
+            out.push_str(&v.to_string());
+        } else if let Ok(Some(v)) = value.get::<i64>() {
+            out.push_str(&v.to_string());
+        } else if let Ok(Some(v)) = value.get::<f32>() {
+            out.push_str(&v.to_string());
+        } else if let Ok(Some(v)) = value.get::<bool>() {
+            out.push_str(&v.to_string());
+        } else {
+            out.push_str("null");
         }
     }
+    out.push('}');
+    out
 }
 
-fn display_table(
-    table_content_store: &gtk::TreeStore,
-    col_count: usize,
-    table: Table,
-    range: Range<usize>,
-) -> usize {
-    let mut buffer: Vec<RefField> = Vec::with_capacity(col_count);
-    let mut gtval: Vec<&'static dyn glib::ToValue> = Vec::with_capacity(col_count);
-    let mut gtidx = Vec::with_capacity(col_count);
-
-    table_content_store.clear();
-
-    let mut count: usize = 0;
-
-    for row in table.row_iter() {
-        if !range.contains(&count) {
-            count += 1;
-            continue;
+/// Columns of the sidebar's `gtk::TreeStore`: the label to display, and
+/// whether the row is a group (true) or a leaf table (false).
+const SCHEMA_COL_NAME: u32 = 0;
+const SCHEMA_COL_IS_GROUP: u32 = 1;
+
+fn insert_schema_item(
+    store: &gtk::TreeStore,
+    parent: Option<&gtk::TreeIter>,
+    item: &DatabaseTreeItem,
+) {
+    match item {
+        DatabaseTreeItem::Table { name } => {
+            store.insert_with_values(
+                parent,
+                None,
+                &[SCHEMA_COL_NAME, SCHEMA_COL_IS_GROUP],
+                &[name, &false],
+            );
         }
+        DatabaseTreeItem::Group { name, children } => {
+            let iter = store.insert_with_values(
+                parent,
+                None,
+                &[SCHEMA_COL_NAME, SCHEMA_COL_IS_GROUP],
+                &[name, &true],
+            );
+            for child in children {
+                insert_schema_item(store, Some(&iter), child);
+            }
+        }
+    }
+}
 
-        buffer.clear();
-        gtval.clear();
-        gtidx.clear();
+fn schema_row_is_group(model: &gtk::TreeModel, iter: &gtk::TreeIter) -> bool {
+    match model
+        .get_value(iter, SCHEMA_COL_IS_GROUP as i32)
+        .get::<bool>()
+    {
+        Ok(Some(v)) => v,
+        _ => false,
+    }
+}
 
-        for (i, field) in row.field_iter().enumerate() {
-            if let Some(r) = RefField::from(field) {
-                buffer.push(r);
-                let cidex_u32 = u32::try_from(i).unwrap();
-                gtidx.push(cidex_u32);
+fn schema_row_name(model: &gtk::TreeModel, iter: &gtk::TreeIter) -> glib::GString {
+    match model
+        .get_value(iter, SCHEMA_COL_NAME as i32)
+        .get::<String>()
+    {
+        Ok(Some(v)) => glib::GString::from(v),
+        _ => glib::GString::from(""),
+    }
+}
+
+/// Whether `iter` is a table whose name contains `query`, or a group with
+/// such a table anywhere below it - the tree-store equivalent of
+/// [`DatabaseTreeItem::matches`], used once names only live in the store.
+fn schema_row_matches(model: &gtk::TreeModel, iter: &gtk::TreeIter, query: &str) -> bool {
+    if !schema_row_is_group(model, iter) {
+        return schema_row_name(model, iter).to_lowercase().contains(query);
+    }
+    match model.iter_children(Some(iter)) {
+        Some(child) => loop {
+            if schema_row_matches(model, &child, query) {
+                return true;
             }
-        }
+            if !model.iter_next(&child) {
+                return false;
+            }
+        },
+        None => false,
+    }
+}
 
-        for f in &buffer {
-            match f {
-                RefField::Integer(int_val) => {
-                    let v: &'static i32 = unsafe { std::mem::transmute(int_val) };
-                    gtval.push(v);
-                }
-                RefField::Float(float_val) => {
-                    let v: &'static f32 = unsafe { std::mem::transmute(float_val) };
-                    gtval.push(v);
-                }
-                RefField::Text(str_val) => {
-                    let v: &'static String = unsafe { std::mem::transmute(str_val) };
-                    gtval.push(v);
-                }
-                RefField::Boolean(bool_val) => {
-                    let v: &'static bool = unsafe { std::mem::transmute(bool_val) };
-                    gtval.push(v);
-                }
-                RefField::BigInt(int_val) => {
-                    let v: &'static i64 = unsafe { std::mem::transmute(int_val) };
-                    gtval.push(v);
-                }
-                RefField::VarChar(str_val) => {
-                    let v: &'static String = unsafe { std::mem::transmute(str_val) };
-                    gtval.push(v);
-                }
+/// The path to the first table (leaf) row in the sidebar, descending into
+/// groups as needed - used to select an initial table once a file loads.
+fn first_leaf_path(
+    model: &gtk::TreeModel,
+    parent: Option<&gtk::TreeIter>,
+) -> Option<gtk::TreePath> {
+    let mut child = model.iter_children(parent)?;
+    loop {
+        if schema_row_is_group(model, &child) {
+            if let Some(path) = first_leaf_path(model, Some(&child)) {
+                return Some(path);
             }
+        } else {
+            return model.get_path(&child);
+        }
+        if !model.iter_next(&child) {
+            return None;
         }
-        table_content_store.insert_with_values(None, None, &gtidx[..], &gtval[..]);
-        count += 1;
     }
-    count
 }
 
 fn main() {
@@ -257,12 +321,12 @@ fn main() {
     let button_open: gtk::Button = builder.get_object("button-open").unwrap();
     let button_export: gtk::Button = builder.get_object("button-export").unwrap();
     let button_search: gtk::ToggleButton = builder.get_object("button-search").unwrap();
-    let button_next: gtk::Button = builder.get_object("button-next").unwrap();
-    let button_previous: gtk::Button = builder.get_object("button-previous").unwrap();
+    // The lazy, fdb-backed tree model asks GTK to fetch only the rows it
+    // draws, so the paging controls the old 1024-row-page store needed are
+    // kept hidden.
     let button_box_paging: gtk::ButtonBox = builder.get_object("button-box-paging").unwrap();
     let label_page: gtk::Label = builder.get_object("label-page").unwrap();
 
-    // TODO: maybe later
     button_box_paging.set_visible(false);
 
     /*let hsize_group = gtk::SizeGroupBuilder::new()
@@ -281,74 +345,57 @@ fn main() {
     searchbar.add(&entry);
     searchbar.set_hexpand(false);
 
-    let listbox = gtk::ListBox::new();
-    listbox.get_style_context().add_class("fdb-table-list");
-    listbox.set_size_request(250, 250);
-
-    let add_table_row = {
-        let listbox = listbox.clone();
-        move |table: Table| {
-            let name = table.name();
-            let n = name.as_ref();
-            let lbl = gtk::LabelBuilder::new().label(n).xalign(0.0).build();
-            let row = gtk::ListBoxRow::new();
-            row.get_style_context().add_class("fdb-table");
-            row.add(&lbl);
-            listbox.add(&row);
-        }
-    };
-
-    /*listbox.set_header_func(Some(Box::new(|row: &gtk::ListBoxRow, before| {
-        if before.is_some() && row.get_header().is_none() {
-            let sep = gtk::SeparatorBuilder::new()
-                .orientation(gtk::Orientation::Horizontal)
-                .build();
-            row.set_header(Some(&sep));
-        }
-    })));*/
-
-    button_search.connect_toggled({
-        let searchbar = searchbar.clone();
-        let entry = entry.clone();
-        let listbox = listbox.clone();
-
-        move |btn| {
-            if btn.get_active() {
-                searchbar.set_search_mode(true);
-                entry.grab_focus();
-                listbox.set_filter_func({
-                    let entry = entry.clone();
-                    Some(Box::new(move |row: &gtk::ListBoxRow| {
-                        let search = entry.get_text();
-                        if search.is_empty() {
-                            return true;
-                        }
+    // The sidebar groups tables by shared name prefix, so hundreds of
+    // tables stay navigable instead of one long flat list.
+    let schema_store = gtk::TreeStore::new(&[String::static_type(), bool::static_type()]);
+    let schema_filter = gtk::TreeModelFilter::new(&schema_store, None);
+    let schema_query: Rc<RefCell<String>> = Rc::new(RefCell::new(String::new()));
 
-                        let label: gtk::Label = row.get_child().unwrap().downcast().unwrap();
-                        let name = label.get_text();
-                        name.contains(search.as_str())
-                    }))
-                });
-            } else {
-                listbox.set_filter_func(None);
-                searchbar.set_search_mode(false);
-                entry.set_text("");
+    let schema_tree_view = gtk::TreeViewBuilder::new()
+        .model(&schema_filter)
+        .headers_visible(false)
+        .build();
+    schema_tree_view
+        .get_style_context()
+        .add_class("fdb-table-list");
+    schema_tree_view.set_size_request(250, 250);
+
+    let schema_column = gtk::TreeViewColumn::new();
+    let schema_cell = gtk::CellRendererText::new();
+    schema_column.pack_start(&schema_cell, true);
+    schema_column.add_attribute(&schema_cell, "text", SCHEMA_COL_NAME as i32);
+    schema_tree_view.append_column(&schema_column);
+
+    schema_tree_view
+        .get_selection()
+        .set_select_function(Some(Box::new({
+            let schema_filter = schema_filter.clone();
+            move |_selection, _model, path, _currently_selected| match schema_filter.get_iter(path)
+            {
+                Some(iter) => !schema_row_is_group(schema_filter.upcast_ref(), &iter),
+                None => true,
             }
-        }
-    });
+        })));
 
-    entry.connect_search_changed({
-        let listbox = listbox.clone();
-        move |_entry| {
-            listbox.invalidate_filter();
+    schema_filter.set_visible_func({
+        let schema_query = schema_query.clone();
+        move |model, iter| {
+            let query = schema_query.borrow();
+            query.is_empty() || schema_row_matches(model, iter, &query)
         }
     });
 
+    let content_query: Rc<RefCell<String>> = Rc::new(RefCell::new(String::new()));
+    let content_filter: Rc<RefCell<Option<gtk::TreeModelFilter>>> = Rc::new(RefCell::new(None));
+    // Every table name, kept around so "Follow reference..." can offer a
+    // target-table picker without re-scanning the database.
+    let all_table_names: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+
     let scroll = gtk::ScrolledWindowBuilder::new()
         .hscrollbar_policy(gtk::PolicyType::Never)
         .vscrollbar_policy(gtk::PolicyType::Automatic)
         .build();
-    scroll.add(&listbox);
+    scroll.add(&schema_tree_view);
 
     left_box.pack_start(&searchbar, false, false, 0);
     left_box.pack_start(&scroll, true, true, 0);
@@ -361,177 +408,588 @@ fn main() {
 
     scroll2.add(&table_content_view);
 
+    // A primary-key lookup that jumps straight to a row via its bucket's
+    // chain, instead of scrolling through a linear scan of the table.
+    let goto_box = gtk::BoxBuilder::new()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    let goto_entry = gtk::EntryBuilder::new()
+        .placeholder_text("Jump to row by id...")
+        .build();
+    let goto_status = gtk::Label::new(None);
+    goto_box.pack_start(&gtk::Label::new(Some("Row ID:")), false, false, 4);
+    goto_box.pack_start(&goto_entry, false, false, 0);
+    goto_box.pack_start(&goto_status, false, false, 4);
+
+    let right_box = gtk::BoxBuilder::new()
+        .orientation(gtk::Orientation::Vertical)
+        .build();
+    right_box.pack_start(&goto_box, false, false, 4);
+    right_box.pack_start(&scroll2, true, true, 0);
+
     pane_container.add1(&left_box);
     pane_container.set_child_shrink(&left_box, false);
-    pane_container.add2(&scroll2);
+    pane_container.add2(&right_box);
+
+    // Numeric column types get a numeric `set_sort_func` instead of the
+    // default lexical string comparison `TreeModelSort` would otherwise use.
+    fn is_numeric_type(value_type: ValueType) -> bool {
+        matches!(
+            value_type,
+            ValueType::Integer | ValueType::Float | ValueType::BigInt
+        )
+    }
+
+    fn numeric_cmp(
+        model: &gtk::TreeModel,
+        a: &gtk::TreeIter,
+        b: &gtk::TreeIter,
+        col: i32,
+    ) -> std::cmp::Ordering {
+        let va = model.get_value(a, col);
+        let vb = model.get_value(b, col);
+        if let (Ok(Some(x)), Ok(Some(y))) = (va.get::<i64>(), vb.get::<i64>()) {
+            return x.cmp(&y);
+        }
+        if let (Ok(Some(x)), Ok(Some(y))) = (va.get::<f32>(), vb.get::<f32>()) {
+            return x.partial_cmp(&y).unwrap_or(std::cmp::Ordering::Equal);
+        }
+        if let (Ok(Some(x)), Ok(Some(y))) = (va.get::<i32>(), vb.get::<i32>()) {
+            return x.cmp(&y);
+        }
+        std::cmp::Ordering::Equal
+    }
 
-    fn append_text_column(tree: &TreeView, name: &str, col_index: usize) {
+    // Clicking the same header a second time reverses the sort, a third
+    // click drops back to unsorted storage order.
+    fn append_sortable_column(
+        tree: &TreeView,
+        model_sort: &gtk::TreeModelSort,
+        filter: &gtk::TreeModelFilter,
+        content_model: &FdbTreeModel,
+        name: &str,
+        col_index: usize,
+        value_type: ValueType,
+        sort_state: &Rc<RefCell<ColumnSort>>,
+    ) -> gtk::TreeViewColumn {
         let column = gtk::TreeViewColumn::new();
         let cell = gtk::CellRendererText::new();
 
         column.pack_start(&cell, true);
         column.set_title(name);
+        column.set_resizable(true);
+        column.set_clickable(true);
         let cidx_i32 = i32::try_from(col_index).unwrap();
         column.add_attribute(&cell, "text", cidx_i32);
+
+        // Editing goes through the sort/filter wrappers' paths, which have
+        // to be unwound back to a row index in `content_model` before the
+        // edit can be written anywhere.
+        cell.set_editable(true);
+        cell.connect_edited({
+            let model_sort = model_sort.clone();
+            let filter = filter.clone();
+            let content_model = content_model.clone();
+            move |_cell, sorted_path, new_text| {
+                let filter_path = match model_sort.convert_path_to_child_path(&sorted_path) {
+                    Some(path) => path,
+                    None => return,
+                };
+                let base_path = match filter.convert_path_to_child_path(&filter_path) {
+                    Some(path) => path,
+                    None => return,
+                };
+                let row_index = match base_path.get_indices().first() {
+                    Some(&row) => row as usize,
+                    None => return,
+                };
+                if let Some(value) = parse_edit(value_type, new_text) {
+                    content_model.set_value(row_index, col_index, value);
+                }
+            }
+        });
+
+        if is_numeric_type(value_type) {
+            model_sort.set_sort_func(gtk::SortColumn::Index(cidx_i32 as u32), move |m, a, b| {
+                numeric_cmp(m, a, b, cidx_i32)
+            });
+        }
+
+        column.connect_clicked({
+            let model_sort = model_sort.clone();
+            let sort_state = sort_state.clone();
+            move |_col| {
+                let mut state = sort_state.borrow_mut();
+                let next = if state.column == Some(cidx_i32) {
+                    if !state.reverse {
+                        Some(true)
+                    } else {
+                        None
+                    }
+                } else {
+                    Some(false)
+                };
+
+                match next {
+                    Some(reverse) => {
+                        let order = if reverse {
+                            gtk::SortType::Descending
+                        } else {
+                            gtk::SortType::Ascending
+                        };
+                        model_sort
+                            .set_sort_column_id(gtk::SortColumn::Index(cidx_i32 as u32), order);
+                        *state = ColumnSort {
+                            column: Some(cidx_i32),
+                            reverse,
+                        };
+                    }
+                    None => {
+                        model_sort
+                            .set_sort_column_id(gtk::SortColumn::Default, gtk::SortType::Ascending);
+                        *state = ColumnSort::default();
+                    }
+                }
+            }
+        });
+
         tree.append_column(&column);
+
+        // Header context menu: right-click a column to hide it, keeping
+        // wide LEGO Universe tables readable without losing the data.
+        // `TreeViewColumn` isn't a widget and has no signals of its own -
+        // the click has to be caught on its header button, which only
+        // exists once the column has been appended to the tree.
+        if let Some(button) = column.get_button() {
+            button.connect_button_press_event({
+                let column_weak = column.downgrade();
+                move |_button, event| {
+                    if event.get_button() == 3 {
+                        if let Some(column) = column_weak.upgrade() {
+                            let menu = gtk::Menu::new();
+                            let item = gtk::CheckMenuItemBuilder::new()
+                                .label("Visible")
+                                .active(true)
+                                .build();
+                            item.connect_toggled({
+                                let column = column.clone();
+                                move |item| {
+                                    column.set_visible(item.get_active());
+                                }
+                            });
+                            menu.append(&item);
+                            menu.show_all();
+                            menu.popup_easy(event.get_button(), event.get_time());
+                        }
+                        return gtk::Inhibit(true);
+                    }
+                    gtk::Inhibit(false)
+                }
+            });
+        }
+
+        column
     }
 
-    let paging = Rc::new(RefCell::new(None));
     let page: Rc<RefCell<Option<TablePage>>> = Rc::new(RefCell::new(None));
 
-    let set_paging = {
-        let paging = paging.clone();
-        //let button_box_paging = button_box_paging.clone();
-        let button_previous = button_previous.clone();
-        let button_next = button_next.clone();
-        //let label_page = label_page.clone();
-        move |new: Option<Paging>| {
-            *paging.borrow_mut() = new;
-            if let Some(p) = new {
-                button_box_paging.set_visible(true);
-                label_page.set_text(&format!("{}/{}", p.current + 1, p.num_pages));
-                button_next.set_sensitive(p.current + 1 < p.num_pages);
-                button_previous.set_sensitive(p.current > 0);
+    let update_count_label = {
+        let label_page = label_page.clone();
+        let content_query = content_query.clone();
+        move |p: &TablePage| {
+            if content_query.borrow().is_empty() {
+                label_page.set_text(&format!("{} rows", p.model.row_count()));
             } else {
-                button_box_paging.set_visible(false);
+                label_page.set_text(&format!("{} matches", p.filter.iter_n_children(None)));
             }
         }
     };
 
-    button_previous.connect_clicked({
-        let page = page.clone();
+    // Shared by picking a table in the sidebar and by "Follow reference..."
+    // jumping into a different table from the content view.
+    let load_table_by_name: Rc<dyn Fn(glib::GString)> = Rc::new({
+        let table_content_view = table_content_view.clone();
         let database_memmap = database_memmap.clone();
-        let paging = paging.clone();
-        let set_paging = set_paging.clone();
-        move |_button_next| {
-            if let Some(page) = page.borrow().deref() {
-                let opt = *paging.borrow();
-                if let Some(paging) = opt {
-                    let b = database_memmap.borrow();
-                    let mmap = &b.as_ref().unwrap().mmap[..];
-                    let db: Database = Database::new(mmap);
+        let content_query = content_query.clone();
+        let content_filter = content_filter.clone();
+        let page = page.clone();
+        let update_count_label = update_count_label.clone();
+        move |name: glib::GString| {
+            table_content_view.set_model::<gtk::TreeModelSort>(None);
+
+            for col in table_content_view.get_columns() {
+                table_content_view.remove_column(&col);
+            }
+
+            let b = database_memmap.borrow();
+            let mmap_rc = b.as_ref().unwrap().mmap.clone();
+            drop(b);
+
+            let db: Database = Database::new(&mmap_rc[..]);
+            let tables = db.tables();
+            let table = tables.by_name(name.as_str()).unwrap();
 
-                    let tables = db.tables();
-                    let table = tables.by_name(page.name.as_str()).unwrap();
+            let col_count = table.column_count();
 
-                    let current = paging.current - 1;
-                    let new_min = current * 1024;
-                    let new_max = new_min + 1024;
-                    display_table(&page.store, table.column_count(), table, new_min..new_max);
+            let content_model = FdbTreeModel::new();
+            content_model.set_table(mmap_rc, table);
 
-                    set_paging(Some(Paging {
-                        current,
-                        num_pages: paging.num_pages,
-                    }))
+            let filter = gtk::TreeModelFilter::new(&content_model, None);
+            filter.set_visible_func({
+                let content_query = content_query.clone();
+                move |model, iter| {
+                    let query = content_query.borrow();
+                    if query.is_empty() {
+                        return true;
+                    }
+                    row_contains(model, iter, col_count, &RowQuery::parse(&query))
                 }
+            });
+
+            let model_sort = gtk::TreeModelSort::new(&filter);
+            let sort_state = Rc::new(RefCell::new(ColumnSort::default()));
+
+            for (col_index, tcol) in table.column_iter().enumerate() {
+                let value_type = content_model
+                    .column_value_type(col_index)
+                    .unwrap_or(ValueType::Text);
+                append_sortable_column(
+                    &table_content_view,
+                    &model_sort,
+                    &filter,
+                    &content_model,
+                    tcol.name().as_ref(),
+                    col_index,
+                    value_type,
+                    &sort_state,
+                );
             }
+
+            table_content_view.set_model(Some(&model_sort));
+
+            *content_filter.borrow_mut() = Some(filter.clone());
+
+            let table_page = TablePage {
+                name,
+                model: content_model,
+                filter,
+                col_count,
+            };
+            update_count_label(&table_page);
+            *page.borrow_mut() = Some(table_page);
         }
     });
 
-    button_next.connect_clicked({
-        let page = page.clone();
-        let database_memmap = database_memmap.clone();
-        //let paging = paging.clone();
-        let set_paging = set_paging.clone();
-        move |_button_next| {
-            if let Some(page) = page.borrow().deref() {
-                let opt = *paging.borrow();
-                if let Some(paging) = opt {
-                    let b = database_memmap.borrow();
-                    let mmap = &b.as_ref().unwrap().mmap[..];
-                    let db: Database = Database::new(mmap);
-
-                    let tables = db.tables();
-                    let table = tables.by_name(page.name.as_str()).unwrap();
+    schema_tree_view.get_selection().connect_changed({
+        let load_table_by_name = load_table_by_name.clone();
+        move |selection| {
+            if let Some((model, iter)) = selection.get_selected() {
+                load_table_by_name(schema_row_name(&model, &iter));
+            } else {
+                println!("Unselect Row")
+            }
+        }
+    });
 
-                    let current = paging.current + 1;
-                    let new_min = current * 1024;
-                    let new_max = new_min + 1024;
-                    display_table(&page.store, table.column_count(), table, new_min..new_max);
+    schema_tree_view.connect_row_activated({
+        let schema_tree_view = schema_tree_view.clone();
+        move |_tree, path, _column| {
+            if schema_tree_view.row_expanded(path) {
+                schema_tree_view.collapse_row(path);
+            } else {
+                schema_tree_view.expand_row(path, false);
+            }
+        }
+    });
 
-                    set_paging(Some(Paging {
-                        current,
-                        num_pages: paging.num_pages,
-                    }))
+    // Resolves a primary-key query against whichever table is on screen and
+    // selects/scrolls to the matching row - shared by the id entry above the
+    // content view and by "Follow reference..." in its right-click popover.
+    let jump_to_pk: Rc<dyn Fn(&pklookup::PkQuery) -> bool> = Rc::new({
+        let database_memmap = database_memmap.clone();
+        let page = page.clone();
+        let table_content_view = table_content_view.clone();
+        move |query: &pklookup::PkQuery| -> bool {
+            let page_ref = page.borrow();
+            let found = (|| {
+                let p = page_ref.as_ref()?;
+                let b = database_memmap.borrow();
+                let mmap = &b.as_ref()?.mmap[..];
+                let db: Database = Database::new(mmap);
+                let table = db.tables().by_name(p.name.as_str())?;
+                let row = pklookup::find_row_by_pk(&table, query)?;
+                let row_index = p.model.row_for_offset(row.offset())?;
+
+                let child_path = gtk::TreePath::from_indicesv(&[row_index as i32]);
+                let filter_path = p.filter.convert_child_path_to_path(&child_path)?;
+                let model_sort: gtk::TreeModelSort =
+                    table_content_view.get_model()?.downcast().ok()?;
+                model_sort.convert_child_path_to_path(&filter_path)
+            })();
+
+            match found {
+                Some(sort_path) => {
+                    let selection = table_content_view.get_selection();
+                    selection.select_path(&sort_path);
+                    table_content_view.scroll_to_cell(
+                        Some(&sort_path),
+                        None::<&gtk::TreeViewColumn>,
+                        false,
+                        0.0,
+                        0.0,
+                    );
+                    true
                 }
+                None => false,
             }
         }
     });
 
-    listbox.connect_row_selected({
-        //let table_content_view = table_content_view.clone();
-        let database_memmap = database_memmap.clone();
-        //let page = page.clone();
-        //let set_paging = set_paging.clone();
-        move |_list, obj| {
-            if let Some(row) = obj {
-                table_content_view.set_model::<gtk::TreeStore>(None);
-
-                for col in table_content_view.get_columns() {
-                    table_content_view.remove_column(&col);
+    goto_entry.connect_activate({
+        let jump_to_pk = jump_to_pk.clone();
+        let goto_status = goto_status.clone();
+        move |entry| {
+            let query = pklookup::PkQuery::parse(entry.get_text().as_str());
+            goto_status.set_text(if jump_to_pk(&query) { "" } else { "not found" });
+        }
+    });
+
+    // Right-click on the content view: operate on the whole selection
+    // (select all / reverse / copy value / copy as JSON), or - given the
+    // clicked cell's integer value - jump to the row with that primary key
+    // in a target table picked from a list of every table.
+    table_content_view.connect_button_press_event({
+        let load_table_by_name = load_table_by_name.clone();
+        let jump_to_pk = jump_to_pk.clone();
+        let all_table_names = all_table_names.clone();
+        move |tree, event| {
+            if event.get_button() != 3 {
+                return gtk::Inhibit(false);
+            }
+            let (x, y) = event.get_position();
+            let hit = match tree.get_path_at_pos(x as i32, y as i32) {
+                Some((Some(path), column, _, _)) => (path, column),
+                _ => return gtk::Inhibit(false),
+            };
+            let (path, column) = hit;
+
+            let selection = tree.get_selection();
+            if !selection.path_is_selected(&path) {
+                selection.unselect_all();
+                selection.select_path(&path);
+            }
+
+            let model = match tree.get_model() {
+                Some(model) => model,
+                None => return gtk::Inhibit(false),
+            };
+            let column_names: Vec<String> = tree
+                .get_columns()
+                .iter()
+                .map(|c| c.get_title().map(|t| t.to_string()).unwrap_or_default())
+                .collect();
+            let clicked_col_index = column
+                .as_ref()
+                .and_then(|column| tree.get_columns().iter().position(|c| c == column))
+                .unwrap_or(0);
+
+            let popover = gtk::Popover::new(Some(tree));
+            if let Some(column) = column.as_ref() {
+                let cell_area = tree.get_cell_area(Some(&path), Some(column));
+                popover.set_pointing_to(&cell_area);
+            }
+
+            let menu_box = gtk::BoxBuilder::new()
+                .orientation(gtk::Orientation::Vertical)
+                .build();
+
+            let select_all_btn = gtk::ModelButtonBuilder::new().label("Select All").build();
+            select_all_btn.connect_clicked({
+                let selection = selection.clone();
+                move |_| selection.select_all()
+            });
+            menu_box.add(&select_all_btn);
+
+            let reverse_btn = gtk::ModelButtonBuilder::new()
+                .label("Reverse Selection")
+                .build();
+            reverse_btn.connect_clicked({
+                let selection = selection.clone();
+                let model = model.clone();
+                move |_| {
+                    if let Some(iter) = model.get_iter_first() {
+                        loop {
+                            if let Some(path) = model.get_path(&iter) {
+                                if selection.path_is_selected(&path) {
+                                    selection.unselect_path(&path);
+                                } else {
+                                    selection.select_path(&path);
+                                }
+                            }
+                            if !model.iter_next(&iter) {
+                                break;
+                            }
+                        }
+                    }
+                }
+            });
+            menu_box.add(&reverse_btn);
+
+            let copy_value_btn = gtk::ModelButtonBuilder::new().label("Copy Value").build();
+            copy_value_btn.connect_clicked({
+                let selection = selection.clone();
+                move |_| {
+                    let (paths, model) = selection.get_selected_rows();
+                    let text: Vec<String> = paths
+                        .iter()
+                        .filter_map(|p| model.get_iter(p))
+                        .map(|iter| value_to_string(&model, &iter, clicked_col_index as i32))
+                        .collect();
+                    gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD).set_text(&text.join("\n"));
                 }
+            });
+            menu_box.add(&copy_value_btn);
 
-                let label: gtk::Label = row.get_child().unwrap().downcast().unwrap();
-                let name = label.get_text();
+            let copy_json_btn = gtk::ModelButtonBuilder::new()
+                .label("Copy Row as JSON")
+                .build();
+            copy_json_btn.connect_clicked({
+                let selection = selection.clone();
+                let column_names = column_names.clone();
+                move |_| {
+                    let (paths, model) = selection.get_selected_rows();
+                    let rows: Vec<String> = paths
+                        .iter()
+                        .filter_map(|p| model.get_iter(p))
+                        .map(|iter| row_to_json(&model, &iter, &column_names))
+                        .collect();
+                    let text = format!("[{}]", rows.join(","));
+                    gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD).set_text(&text);
+                }
+            });
+            menu_box.add(&copy_json_btn);
+
+            // Only offer to follow a reference if the clicked cell actually
+            // looks like an id (a plain integer).
+            let clicked_value = model.get_iter(&path).and_then(|iter| {
+                let value = model.get_value(&iter, clicked_col_index as i32);
+                value
+                    .get::<i64>()
+                    .ok()
+                    .flatten()
+                    .or_else(|| value.get::<i32>().ok().flatten().map(i64::from))
+            });
 
-                let b = database_memmap.borrow();
-                let mmap = &b.as_ref().unwrap().mmap[..];
-                let db: Database = Database::new(mmap);
+            if let Some(pk_value) = clicked_value {
+                menu_box.add(&gtk::SeparatorBuilder::new().build());
+                menu_box.add(&gtk::Label::new(Some("Follow reference...")));
 
-                let tables = db.tables();
-                let table = tables.by_name(name.as_str()).unwrap();
-
-                let col_count = table.column_count();
-                let mut gtcol = Vec::with_capacity(col_count);
-
-                for (col_index, tcol) in table.column_iter().enumerate() {
-                    let typ = match tcol.value_type() {
-                        ValueType::Nothing => String::static_type(),
-                        ValueType::Integer => i32::static_type(),
-                        ValueType::Float => f32::static_type(),
-                        ValueType::Text => String::static_type(),
-                        ValueType::Boolean => bool::static_type(),
-                        ValueType::BigInt => i64::static_type(),
-                        ValueType::VarChar => String::static_type(),
-                        ValueType::Unknown(k) => panic!("Column type unknown {}", k),
-                    };
-                    gtcol.push(typ);
-                    append_text_column(&table_content_view, tcol.name().as_ref(), col_index);
+                let target_combo = gtk::ComboBoxTextBuilder::new().build();
+                for name in all_table_names.borrow().iter() {
+                    target_combo.append(Some(name), name);
                 }
+                menu_box.add(&target_combo);
+
+                let follow_btn = gtk::ModelButtonBuilder::new().label("Go").build();
+                follow_btn.connect_clicked({
+                    let load_table_by_name = load_table_by_name.clone();
+                    let jump_to_pk = jump_to_pk.clone();
+                    let target_combo = target_combo.clone();
+                    let popover = popover.downgrade();
+                    move |_| {
+                        if let Some(target) = target_combo.get_active_text() {
+                            load_table_by_name(target);
+                            jump_to_pk(&pklookup::PkQuery::Integer(pk_value));
+                        }
+                        if let Some(popover) = popover.upgrade() {
+                            popover.popdown();
+                        }
+                    }
+                });
+                menu_box.add(&follow_btn);
+            }
 
-                let table_content_store = gtk::TreeStore::new(&gtcol[..]);
-                let max = display_table(&table_content_store, col_count, table, 0..1024);
-                let num_pages = (max / 1024) + 1;
+            popover.add(&menu_box);
+            menu_box.show_all();
+            popover.popup();
 
-                set_paging(Some(Paging {
-                    num_pages,
-                    current: 0,
-                }));
+            gtk::Inhibit(true)
+        }
+    });
 
-                table_content_view.set_model(Some(&table_content_store));
+    button_search.connect_toggled({
+        let searchbar = searchbar.clone();
+        let entry = entry.clone();
+        let schema_tree_view = schema_tree_view.clone();
+        let schema_filter = schema_filter.clone();
+        let schema_query = schema_query.clone();
+        let content_query = content_query.clone();
+        let content_filter = content_filter.clone();
+        let page = page.clone();
+        let update_count_label = update_count_label.clone();
 
-                *page.borrow_mut() = Some(TablePage {
-                    name,
-                    store: table_content_store,
-                });
+        move |btn| {
+            if btn.get_active() {
+                searchbar.set_search_mode(true);
+                entry.grab_focus();
             } else {
-                println!("Unselect Row")
+                searchbar.set_search_mode(false);
+                entry.set_text("");
+                schema_query.borrow_mut().clear();
+                schema_filter.refilter();
+                schema_tree_view.collapse_all();
+                content_query.borrow_mut().clear();
+                if let Some(filter) = content_filter.borrow().as_ref() {
+                    filter.refilter();
+                }
+                if let Some(p) = page.borrow().as_ref() {
+                    update_count_label(p);
+                }
+            }
+        }
+    });
+
+    entry.connect_search_changed({
+        let schema_tree_view = schema_tree_view.clone();
+        let schema_filter = schema_filter.clone();
+        let schema_query = schema_query.clone();
+        let content_query = content_query.clone();
+        let content_filter = content_filter.clone();
+        let page = page.clone();
+        let update_count_label = update_count_label.clone();
+
+        move |entry| {
+            let query = entry.get_text().to_lowercase();
+
+            *schema_query.borrow_mut() = query.clone();
+            schema_filter.refilter();
+            if query.is_empty() {
+                schema_tree_view.collapse_all();
+            } else {
+                schema_tree_view.expand_all();
+            }
+
+            *content_query.borrow_mut() = query;
+
+            if let Some(filter) = content_filter.borrow().as_ref() {
+                filter.refilter();
+            }
+
+            if let Some(p) = page.borrow().as_ref() {
+                update_count_label(p);
             }
         }
     });
 
     let load = {
-        //let listbox = listbox.clone();
         let database_memmap = database_memmap.clone();
-        let add_table_row = add_table_row.clone();
+        let schema_store = schema_store.clone();
+        let schema_tree_view = schema_tree_view.clone();
         let button_export = button_export.clone();
+        let all_table_names = all_table_names.clone();
         move |db: DB| {
-            listbox.forall({
-                let listbox = listbox.clone();
-                move |child| {
-                    listbox.remove(child);
-                }
-            });
+            schema_store.clear();
 
             *database_memmap.borrow_mut() = Some(db);
 
@@ -541,14 +999,24 @@ fn main() {
             let mmap = &b.as_ref().unwrap().mmap[..];
             let db: Database = Database::new(mmap);
 
-            let tables = db.tables();
-            for table in tables.iter() {
-                add_table_row(table);
+            let mut names: Vec<String> = db
+                .tables()
+                .iter()
+                .map(|table| table.name().as_ref().to_string())
+                .collect();
+            names.sort();
+            *all_table_names.borrow_mut() = names.clone();
+
+            let tree = Tree::from_table_names(names, Some('_'));
+            for item in &tree.roots {
+                insert_schema_item(&schema_store, None, item);
             }
-            listbox.show_all();
+            schema_tree_view.show_all();
 
-            let widget = listbox.get_row_at_index(0);
-            listbox.select_row(widget.as_ref());
+            if let Some(path) = first_leaf_path(schema_store.upcast_ref(), None) {
+                schema_tree_view.expand_to_path(&path);
+                schema_tree_view.get_selection().select_path(&path);
+            }
         }
     };
 
@@ -606,12 +1074,23 @@ fn main() {
             file_chooser.add_button("_Save", gtk::ResponseType::Accept);
 
             file_chooser.set_do_overwrite_confirmation(true);
-            file_chooser.set_current_name("export.sqlite");
+            file_chooser.set_current_name(ExportFormat::Sqlite.default_file_name());
+
+            // The format dropdown is the fallback when the typed file name
+            // has no extension we recognize (e.g. a CSV export directory).
+            let format_combo = gtk::ComboBoxTextBuilder::new().build();
+            format_combo.append(Some("sqlite"), "SQLite database (.sqlite)");
+            format_combo.append(Some("csv"), "CSV, one file per table (folder)");
+            format_combo.append(Some("json"), "JSON Lines (.jsonl)");
+            format_combo.set_active_id(Some("sqlite"));
+            file_chooser.set_extra_widget(&format_combo);
 
             let filter = gtk::FileFilter::new();
             filter.add_pattern("*.sqlite");
             filter.add_pattern("*.db");
-            filter.set_name(Some("SQLite-Files"));
+            filter.add_pattern("*.csv");
+            filter.add_pattern("*.jsonl");
+            filter.set_name(Some("Exportable files"));
             file_chooser.set_filter(&filter);
 
             match file_chooser.run() {
@@ -619,11 +1098,22 @@ fn main() {
                     let file = file_chooser.get_filename().unwrap();
                     println!("{}", file.display());
 
+                    let format = file
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .and_then(ExportFormat::from_extension)
+                        .or_else(|| match format_combo.get_active_id().as_deref() {
+                            Some("csv") => Some(ExportFormat::Csv),
+                            Some("json") => Some(ExportFormat::Json),
+                            _ => Some(ExportFormat::Sqlite),
+                        })
+                        .unwrap();
+
                     let b = database_memmap.borrow();
                     let mmap = &b.as_ref().unwrap().mmap[..];
                     let db: Database = Database::new(mmap);
 
-                    try_export_db(&file, db).unwrap();
+                    export::try_export_db(&file, db, format).unwrap();
                 }
                 gtk::ResponseType::Cancel => {
                     println!("Cancel");
@@ -0,0 +1,393 @@
+//! A `gtk::TreeModel` that reads rows lazily straight from the mmap-backed
+//! FDB, instead of eagerly rebuilding a `gtk::TreeStore` one 1024-row page
+//! at a time.
+//!
+//! The model keeps a table's name and column types plus one `Vec<usize>`
+//! of row byte-offsets into the mmap (built once, when the table is
+//! selected), the last decoded row, and any edited cells. `get_value` walks
+//! straight to the requested row's offset and decodes that row's fields
+//! through `row.field_iter()`, so GTK pays only for the
+//! rows it actually asks to draw - nothing is pre-materialized, and there
+//! is no `std::mem::transmute` faking a `'static` lifetime for a `glib::Value`.
+//!
+//! This is the same lazy-materialization goal a `gio::ListModel` subclass
+//! would chase (`get_n_items` from a cheap count, `get_item` decoding one
+//! row on demand), but `gtk::TreeModel` is the interface the `TreeView` this
+//! app actually renders through understands natively - a `ListModel` would
+//! still need a `TreeModel` adapter (or a GTK4 `ColumnView`) in front of it
+//! to show up in this UI, for no benefit over implementing `TreeModel`
+//! directly, which is what `FdbTreeModelPriv` below does.
+
+use glib::subclass;
+use glib::subclass::prelude::*;
+use glib::subclass::Signal;
+use glib::translate::*;
+use gtk::prelude::*;
+use gtk_sys;
+
+use assembly_data::fdb::{
+    align::{Database, Table},
+    core::ValueType,
+};
+use memmap::Mmap;
+use std::{cell::RefCell, collections::HashMap, os::raw::c_int, rc::Rc};
+
+lazy_static::lazy_static! {
+    static ref SIGNALS: Vec<Signal> = vec![Signal::builder(
+        "cell-edited",
+        &[i32::static_type(), i32::static_type()],
+        glib::Type::Unit,
+    )
+    .build()];
+}
+
+/// Byte offsets of every row of one table, in storage order.
+struct RowIndex {
+    table_name: String,
+    col_count: usize,
+    value_types: Vec<ValueType>,
+    col_types: Vec<glib::Type>,
+    offsets: Vec<usize>,
+    offset_to_row: std::collections::HashMap<usize, usize>,
+}
+
+pub struct FdbTreeModelPriv {
+    mmap: RefCell<Option<Rc<Mmap>>>,
+    index: RefCell<Option<RowIndex>>,
+    stamp: c_int,
+    /// The last row `value_at` decoded, whole, keyed by its row index -
+    /// `get_value` is called once per visible column (and, for the search
+    /// filter, once per column of every row), so
+    /// decoding a full row on its first column and serving the rest from
+    /// here turns what would be one `Database::new` + table lookup per
+    /// cell into one per row.
+    row_cache: RefCell<Option<(usize, Vec<glib::Value>)>>,
+    /// Cells overwritten by [`FdbTreeModel::set_value`], keyed by
+    /// `(row_index, column)`. Checked before `row_cache`/the mmap itself, so
+    /// an edited cell reads back as typed instead of being clobbered by the
+    /// unedited bytes still sitting in the fdb.
+    edits: RefCell<HashMap<(usize, usize), glib::Value>>,
+}
+
+impl ObjectSubclass for FdbTreeModelPriv {
+    const NAME: &'static str = "FdbTreeModel";
+    type ParentType = glib::Object;
+    type Instance = subclass::simple::InstanceStruct<Self>;
+    type Class = subclass::simple::ClassStruct<Self>;
+
+    glib_object_subclass!();
+
+    fn class_init(klass: &mut subclass::simple::ClassStruct<Self>) {
+        klass.add_interface::<gtk::TreeModel>();
+    }
+
+    fn new() -> Self {
+        Self {
+            mmap: RefCell::new(None),
+            index: RefCell::new(None),
+            stamp: 1,
+            row_cache: RefCell::new(None),
+            edits: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+impl ObjectImpl for FdbTreeModelPriv {
+    glib_object_impl!();
+
+    fn signals() -> &'static [Signal] {
+        &SIGNALS
+    }
+}
+
+glib_wrapper! {
+    pub struct FdbTreeModel(Object<subclass::simple::InstanceStruct<FdbTreeModelPriv>, subclass::simple::ClassStruct<FdbTreeModelPriv>, FdbTreeModelClass>)
+        @implements gtk::TreeModel;
+
+    match fn {
+        get_type => || FdbTreeModelPriv::get_type().to_glib(),
+    }
+}
+
+fn gtype_for(value_type: ValueType) -> glib::Type {
+    match value_type {
+        ValueType::Nothing => String::static_type(),
+        ValueType::Integer => i32::static_type(),
+        ValueType::Float => f32::static_type(),
+        ValueType::Text => String::static_type(),
+        ValueType::Boolean => bool::static_type(),
+        ValueType::BigInt => i64::static_type(),
+        ValueType::VarChar => String::static_type(),
+        ValueType::Unknown(_) => String::static_type(),
+    }
+}
+
+impl FdbTreeModel {
+    pub fn new() -> Self {
+        glib::Object::new(Self::static_type(), &[])
+            .unwrap()
+            .downcast()
+            .unwrap()
+    }
+
+    /// Swaps in a new table: scans it once to record where every row
+    /// starts, then forgets the table itself - every later read goes
+    /// straight back to the mmap at the recorded offset.
+    pub fn set_table(&self, mmap: Rc<Mmap>, table: Table) {
+        let priv_ = FdbTreeModelPriv::from_instance(self);
+
+        let col_count = table.column_count();
+        let value_types: Vec<ValueType> = table.column_iter().map(|c| c.value_type()).collect();
+        let col_types: Vec<glib::Type> = value_types.iter().copied().map(gtype_for).collect();
+        let offsets: Vec<usize> = table.row_iter().map(|row| row.offset()).collect();
+        let offset_to_row = offsets
+            .iter()
+            .enumerate()
+            .map(|(row, &offset)| (offset, row))
+            .collect();
+
+        *priv_.mmap.borrow_mut() = Some(mmap);
+        *priv_.index.borrow_mut() = Some(RowIndex {
+            table_name: table.name().as_ref().to_string(),
+            col_count,
+            value_types,
+            col_types,
+            offsets,
+            offset_to_row,
+        });
+        *priv_.row_cache.borrow_mut() = None;
+        priv_.edits.borrow_mut().clear();
+
+        self.row_inserted_range(0, self.row_count());
+    }
+
+    pub fn row_count(&self) -> usize {
+        let priv_ = FdbTreeModelPriv::from_instance(self);
+        priv_
+            .index
+            .borrow()
+            .as_ref()
+            .map(|i| i.offsets.len())
+            .unwrap_or(0)
+    }
+
+    /// The FDB [`ValueType`] of one column, as recorded when [`set_table`]
+    /// indexed the table - the untyped-to-GTK original a caller needs when
+    /// `get_column_type`'s `glib::Type` alone isn't enough, e.g. to tell an
+    /// `Integer` column from a `BigInt` one before parsing an edited cell.
+    pub fn column_value_type(&self, column: usize) -> Option<ValueType> {
+        let priv_ = FdbTreeModelPriv::from_instance(self);
+        priv_
+            .index
+            .borrow()
+            .as_ref()
+            .and_then(|i| i.value_types.get(column).copied())
+    }
+
+    /// The row index (as used by `get_iter`/`get_path`) for the row
+    /// starting at `offset`, if one was indexed by `set_table`.
+    pub fn row_for_offset(&self, offset: usize) -> Option<usize> {
+        let priv_ = FdbTreeModelPriv::from_instance(self);
+        priv_
+            .index
+            .borrow()
+            .as_ref()
+            .and_then(|i| i.offset_to_row.get(&offset).copied())
+    }
+
+    fn row_inserted_range(&self, _from: usize, _count: usize) {
+        // A real implementation emits `row-inserted` per row here; a fresh
+        // `set_model` call on the tree view makes that unnecessary for us,
+        // since every table selection installs a brand new model instance.
+    }
+
+    fn with_row<T>(
+        &self,
+        row_index: usize,
+        f: impl FnOnce(&RowIndex, &[u8], usize) -> T,
+    ) -> Option<T> {
+        let priv_ = FdbTreeModelPriv::from_instance(self);
+        let mmap = priv_.mmap.borrow();
+        let index = priv_.index.borrow();
+        let (mmap, index) = (mmap.as_ref()?, index.as_ref()?);
+        let offset = *index.offsets.get(row_index)?;
+        Some(f(index, &mmap[..], offset))
+    }
+
+    fn value_at(&self, row_index: usize, column: usize) -> glib::Value {
+        let priv_ = FdbTreeModelPriv::from_instance(self);
+
+        if let Some(value) = priv_.edits.borrow().get(&(row_index, column)) {
+            return value.clone();
+        }
+
+        let cached = priv_
+            .row_cache
+            .borrow()
+            .as_ref()
+            .filter(|(cached_row, _)| *cached_row == row_index)
+            .and_then(|(_, values)| values.get(column).cloned());
+        if let Some(value) = cached {
+            return value;
+        }
+
+        let row = self.with_row(row_index, |index, bytes, offset| {
+            let db = Database::new(bytes);
+            let tables = db.tables();
+            let table = tables.by_name(&index.table_name)?;
+            let row = table.row_at(offset)?;
+            Some(
+                row.field_iter()
+                    .map(|field| crate::field_to_value(field))
+                    .collect::<Vec<_>>(),
+            )
+        });
+
+        let values = row.flatten().unwrap_or_default();
+        let value = values
+            .get(column)
+            .cloned()
+            .unwrap_or_else(|| glib::Value::from_type(glib::Type::Invalid));
+        *priv_.row_cache.borrow_mut() = Some((row_index, values));
+        value
+    }
+
+    /// Commits a cell a `CellRendererText::connect_edited` handler parsed
+    /// back into a typed value, then tells every observer about it:
+    /// `row_changed` is `GtkTreeModel`'s own change-notification, which the
+    /// `TreeView`'s bound renderers and the `TreeModelFilter`/`TreeModelSort`
+    /// wrapped on top of us already listen for, and `cell-edited` additionally
+    /// names which column changed for anything that only cares about edits.
+    pub fn set_value(&self, row_index: usize, column: usize, value: glib::Value) {
+        let priv_ = FdbTreeModelPriv::from_instance(self);
+        priv_.edits.borrow_mut().insert((row_index, column), value);
+
+        let iter = iter_for_row(priv_.stamp, row_index);
+        let path = gtk::TreePath::from_indicesv(&[row_index as i32]);
+        self.row_changed(&path, &iter);
+        self.emit("cell-edited", &[&(row_index as i32), &(column as i32)])
+            .expect("cell-edited has no return value");
+    }
+}
+
+/// Packs a row index into a `TreeIter`'s first user-data word - the usual
+/// trick for a flat (non-hierarchical) custom `TreeModel`. Stored as
+/// `row + 1` so row 0 doesn't leave `user_data` zeroed, which GTK (and the
+/// `TreeModelFilter`/`TreeModelSort` wrappers stacked on top of us) treats
+/// as an invalid iter.
+fn iter_for_row(stamp: c_int, row: usize) -> gtk::TreeIter {
+    unsafe {
+        let mut iter: gtk_sys::GtkTreeIter = std::mem::zeroed();
+        iter.stamp = stamp;
+        iter.user_data = (row + 1) as *mut _;
+        from_glib_none(&iter as *const gtk_sys::GtkTreeIter)
+    }
+}
+
+fn row_for_iter(iter: &gtk::TreeIter) -> usize {
+    unsafe {
+        let raw: *const gtk_sys::GtkTreeIter = iter.to_glib_none().0;
+        (*raw).user_data as usize - 1
+    }
+}
+
+impl gtk::subclass::tree_model::TreeModelImpl for FdbTreeModelPriv {
+    fn get_flags(&self, _model: &gtk::TreeModel) -> gtk::TreeModelFlags {
+        gtk::TreeModelFlags::LIST_ONLY
+    }
+
+    fn get_n_columns(&self, _model: &gtk::TreeModel) -> i32 {
+        self.index
+            .borrow()
+            .as_ref()
+            .map_or(0, |i| i.col_count as i32)
+    }
+
+    fn get_column_type(&self, _model: &gtk::TreeModel, index: i32) -> glib::Type {
+        self.index
+            .borrow()
+            .as_ref()
+            .and_then(|i| i.col_types.get(index as usize).copied())
+            .unwrap_or(glib::Type::Invalid)
+    }
+
+    fn get_iter(&self, _model: &gtk::TreeModel, path: &gtk::TreePath) -> Option<gtk::TreeIter> {
+        let indices = path.get_indices();
+        let row = *indices.first()? as usize;
+        if row < self.row_count() {
+            Some(iter_for_row(self.stamp, row))
+        } else {
+            None
+        }
+    }
+
+    fn get_path(&self, _model: &gtk::TreeModel, iter: &gtk::TreeIter) -> Option<gtk::TreePath> {
+        Some(gtk::TreePath::from_indicesv(&[row_for_iter(iter) as i32]))
+    }
+
+    fn get_value(&self, model: &gtk::TreeModel, iter: &gtk::TreeIter, column: i32) -> glib::Value {
+        let this: &FdbTreeModel = model.downcast_ref().expect("model is a FdbTreeModel");
+        this.value_at(row_for_iter(iter), column as usize)
+    }
+
+    fn iter_next(&self, _model: &gtk::TreeModel, iter: &mut gtk::TreeIter) -> bool {
+        let next = row_for_iter(iter) + 1;
+        if next < self.row_count() {
+            *iter = iter_for_row(self.stamp, next);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn iter_children(
+        &self,
+        _model: &gtk::TreeModel,
+        parent: Option<&gtk::TreeIter>,
+    ) -> Option<gtk::TreeIter> {
+        if parent.is_some() {
+            None
+        } else if self.row_count() > 0 {
+            Some(iter_for_row(self.stamp, 0))
+        } else {
+            None
+        }
+    }
+
+    fn iter_has_child(&self, _model: &gtk::TreeModel, _iter: &gtk::TreeIter) -> bool {
+        false
+    }
+
+    fn iter_n_children(&self, _model: &gtk::TreeModel, iter: Option<&gtk::TreeIter>) -> i32 {
+        if iter.is_some() {
+            0
+        } else {
+            self.row_count() as i32
+        }
+    }
+
+    fn iter_nth_child(
+        &self,
+        _model: &gtk::TreeModel,
+        parent: Option<&gtk::TreeIter>,
+        n: i32,
+    ) -> Option<gtk::TreeIter> {
+        if parent.is_some() {
+            return None;
+        }
+        let n = n as usize;
+        if n < self.row_count() {
+            Some(iter_for_row(self.stamp, n))
+        } else {
+            None
+        }
+    }
+
+    fn iter_parent(
+        &self,
+        _model: &gtk::TreeModel,
+        _child: &gtk::TreeIter,
+    ) -> Option<gtk::TreeIter> {
+        None
+    }
+}
@@ -0,0 +1,85 @@
+//! Primary-key bucket lookup.
+//!
+//! FDB tables store their rows in hash buckets keyed by primary key rather
+//! than as a flat array, and `assembly_data::fdb::align` exposes that
+//! layout directly. Looking an integer-keyed row up only has to hash the
+//! id into a bucket index and walk that one bucket's row chain, instead of
+//! scanning every row the way `table.row_iter()` does. The format only
+//! defines that hash for integer keys, so string-keyed tables fall back to
+//! a linear scan instead of guessing at a bucketing scheme.
+
+use assembly_data::fdb::{
+    align::{Field, Row, Table},
+    core::ValueType,
+};
+
+/// A user-typed id, not yet tied to any particular table's column type.
+#[derive(Debug, Clone)]
+pub enum PkQuery {
+    Integer(i64),
+    Text(String),
+}
+
+impl PkQuery {
+    /// Integer ids are the common case, so text is only the fallback for
+    /// values that don't parse as one - this also covers tables whose
+    /// primary key happens to be a `VarChar`/`Text` column, for keys that
+    /// are not themselves integers: a text key such as `"123"` parses as
+    /// `Integer` and does not match a `VarChar`/`Text` key.
+    pub fn parse(text: &str) -> Self {
+        match text.trim().parse::<i64>() {
+            Ok(v) => PkQuery::Integer(v),
+            Err(_) => PkQuery::Text(text.trim().to_string()),
+        }
+    }
+
+    /// The bucket an integer key hashes into, out of `bucket_count` buckets
+    /// - the same `pk_value % bucket_count` the fdb format itself uses to
+    /// place rows.
+    ///
+    /// `key_type` decides the width the hash runs on: a `BigInt` column
+    /// buckets its full 64-bit value, but an `Integer` column only ever
+    /// stores a `u32`, so a negative id (sign-extended to 64 bits by
+    /// `PkQuery::parse`) has to be masked back down to the 32-bit value the
+    /// row itself was bucketed under, or it hashes into the wrong bucket.
+    fn bucket_index(pk: i64, key_type: ValueType, bucket_count: usize) -> usize {
+        let hash = match key_type {
+            ValueType::BigInt => pk as u64 as usize,
+            _ => pk as u32 as usize,
+        };
+        hash % bucket_count
+    }
+}
+
+fn row_key_matches(row: &Row, query: &PkQuery) -> bool {
+    match (row.field_iter().next(), query) {
+        (Some(Field::Integer(v)), PkQuery::Integer(q)) => i64::from(v) == *q,
+        (Some(Field::BigInt(v)), PkQuery::Integer(q)) => v == *q,
+        (Some(Field::Text(v)), PkQuery::Text(q)) => v.decode().as_ref() == q.as_str(),
+        (Some(Field::VarChar(v)), PkQuery::Text(q)) => v.decode().as_ref() == q.as_str(),
+        _ => false,
+    }
+}
+
+/// Finds the row whose first field equals `query`. Integer keys narrow the
+/// search to a single bucket's row chain first - an empty or out-of-range
+/// bucket (or a table with no buckets at all) simply yields no match. Text
+/// keys have no defined bucketing in the fdb format, so they fall back to
+/// a linear scan over every row.
+pub fn find_row_by_pk<'a>(table: &Table<'a>, query: &PkQuery) -> Option<Row<'a>> {
+    match query {
+        PkQuery::Integer(pk) => {
+            let bucket_count = table.bucket_count();
+            if bucket_count == 0 {
+                return None;
+            }
+            let key_type = table
+                .column_iter()
+                .next()
+                .map_or(ValueType::Integer, |col| col.value_type());
+            let bucket = table.bucket_at(PkQuery::bucket_index(*pk, key_type, bucket_count))?;
+            bucket.row_iter().find(|row| row_key_matches(row, query))
+        }
+        PkQuery::Text(_) => table.row_iter().find(|row| row_key_matches(row, query)),
+    }
+}